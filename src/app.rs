@@ -1,107 +1,99 @@
-use std::{
-    future::Future,
-    str::FromStr,
-    sync::{Arc, OnceLock},
-};
+use std::{str::FromStr, sync::Arc, time::Duration};
 
 use anyhow::Context;
 
-use tokio::{runtime::Runtime, sync::oneshot};
-
 use ethers::{
     core::types::Block,
-    types::{BlockId, H256},
+    types::{BlockId, BlockNumber, H256},
 };
 use ethers_providers::{Http, Middleware, Provider};
 
+mod jobs;
+
+use jobs::{Job, JobId, JobQueue};
+
+type BlockResult = anyhow::Result<Option<Block<H256>>>;
+
+/// How often the "Follow head" mode re-fetches the latest block, matched to
+/// Ethereum mainnet's ~12s block time.
+const DEFAULT_POLL_INTERVAL_SECS: f32 = 12.0;
+
+/// A few well-known RPC endpoints offered in the network dropdown; users can
+/// still type any other URL into the text box.
+const NAMED_NETWORKS: &[(&str, &str)] = &[
+    ("Ethereum Mainnet", "https://eth.llamarpc.com"),
+    ("Sepolia", "https://rpc.sepolia.org"),
+    ("Local node", "http://localhost:8545"),
+];
+
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
 pub struct TemplateApp {
-    #[serde(skip)]
-    block: AsyncCell<BlockId, anyhow::Result<Option<Block<H256>>>>,
+    rpc_url: String,
     block_id_selector: String,
+    follow_head: bool,
+    poll_interval_secs: f32,
     #[serde(skip)]
     provider: Arc<Provider<Http>>,
+    /// Scratch buffer for the RPC URL text box, committed to `rpc_url` only
+    /// on focus loss so a provider rebuild isn't triggered on every keystroke.
+    #[serde(skip)]
+    rpc_url_input: Option<String>,
+    /// The `rpc_url` the current `provider` was actually built from, so we
+    /// can tell when the user has edited it and the provider needs rebuilding.
+    #[serde(skip)]
+    provider_rpc_url: String,
+    #[serde(skip)]
+    provider_error: Option<String>,
+    #[serde(skip)]
+    jobs: JobQueue<BlockResult>,
+    /// The selector value and job id of the most recently spawned fetch, so
+    /// we can tell when the selector has changed and cancel the stale job
+    /// rather than let it keep racing against a fresh one.
+    #[serde(skip)]
+    block_job: Option<(BlockId, JobId)>,
+    /// The job id of the in-flight "Follow head" fetch, if any.
+    #[serde(skip)]
+    latest_job: Option<JobId>,
+    /// `egui`'s input time (seconds since start) at which the next "Follow
+    /// head" poll is due.
+    #[serde(skip)]
+    next_poll_at: Option<f64>,
 }
 
-fn get_runtime() -> Arc<Runtime> {
-    static RUNTIME: OnceLock<Arc<Runtime>> = OnceLock::new();
-    RUNTIME
-        .get_or_init(|| Arc::new(Runtime::new().unwrap()))
-        .clone()
-}
-
-pub struct AsyncCell<K, T> {
-    cache: Option<(K, T)>,
-    receiver: Option<(K, oneshot::Receiver<T>)>,
-}
-
-impl<K, T> Default for AsyncCell<K, T> {
-    fn default() -> Self {
-        Self {
-            cache: Default::default(),
-            receiver: Default::default(),
-        }
-    }
-}
-
-impl<K, T> AsyncCell<K, T>
-where
-    T: Send + 'static,
-    K: Eq,
-{
-    pub fn get_or_update<FB, F>(&mut self, key: K, future_builder: FB) -> CellState<&T>
-    where
-        FB: FnOnce() -> F,
-        F: Future<Output = T> + Send + 'static,
-    {
-        if let Some((cached_key, _)) = &self.cache {
-            if cached_key != &key {
-                self.cache = None;
-            }
-        }
-
-        match self.receiver.take() {
-            Some((fetching_key, mut receiver)) => {
-                if let Ok(value) = receiver.try_recv() {
-                    self.cache = Some((fetching_key, value));
-                } else {
-                    self.receiver = Some((fetching_key, receiver));
-                }
-            }
-            None => {
-                let fut = future_builder();
-                let runtime = get_runtime();
-                let (sender, receiver) = oneshot::channel();
-                runtime.spawn(async move { sender.send(fut.await) });
-                self.receiver = Some((key, receiver));
-            }
-        }
-
-        if let Some((_, cached_value)) = &self.cache {
-            CellState::Value(cached_value)
-        } else {
-            CellState::Running
-        }
-    }
-}
-
-pub enum CellState<T> {
-    Running,
-    Value(T),
+// Builds the `Http` transport on a default `reqwest` client (reqwest provides
+// both a native and a wasm32 backend, so this one code path serves both
+// targets). The client is built through `reqwest::Client::builder()` so a bad
+// URL or a client build failure comes back as an error rather than a panic;
+// no client options are set beyond reqwest's defaults.
+fn make_provider(rpc_url: &str) -> anyhow::Result<Provider<Http>> {
+    let url = rpc_url.parse().context("invalid RPC url")?;
+    let client = reqwest::Client::builder()
+        .build()
+        .context("could not build reqwest client")?;
+    Ok(Provider::new(Http::new_with_client(url, client)))
 }
 
 impl Default for TemplateApp {
     fn default() -> Self {
-        let provider = Provider::<Http>::try_from("https://eth.llamarpc.com")
-            .expect("could not instantiate HTTP Provider");
+        let rpc_url = NAMED_NETWORKS[0].1.to_string();
+        let provider = make_provider(&rpc_url).expect("could not instantiate HTTP Provider");
 
         Self {
+            rpc_url: rpc_url.clone(),
             block_id_selector: "0xf45e2dd95ab165ea215c7c3a5001d7f79f52d5685c18ef54d3d046b773d372f2"
                 .to_string(),
-            block: Default::default(),
+            follow_head: false,
+            poll_interval_secs: DEFAULT_POLL_INTERVAL_SECS,
             provider: Arc::new(provider),
+            rpc_url_input: None,
+            provider_rpc_url: rpc_url,
+            provider_error: None,
+            jobs: Default::default(),
+            block_job: None,
+            latest_job: None,
+            next_poll_at: None,
         }
     }
 }
@@ -120,6 +112,146 @@ impl TemplateApp {
 
         Default::default()
     }
+
+    /// Rebuilds `provider` whenever `rpc_url` has changed since it was last
+    /// built, invalidating any cached/in-flight block so the next frame
+    /// refetches against the new endpoint.
+    fn ensure_provider(&mut self) {
+        if self.rpc_url == self.provider_rpc_url {
+            return;
+        }
+
+        match make_provider(&self.rpc_url) {
+            Ok(provider) => {
+                self.provider = Arc::new(provider);
+                self.provider_error = None;
+            }
+            Err(err) => {
+                self.provider_error = Some(err.to_string());
+            }
+        }
+        self.provider_rpc_url = self.rpc_url.clone();
+
+        if let Some((_, job_id)) = self.block_job.take() {
+            self.jobs.cancel(job_id);
+            self.jobs.forget(job_id);
+        }
+        if let Some(job_id) = self.latest_job.take() {
+            self.jobs.cancel(job_id);
+            self.jobs.forget(job_id);
+        }
+        self.next_poll_at = None;
+    }
+
+    /// Makes sure a fetch is running for `block_id`, cancelling whatever was
+    /// previously in flight if the selector has moved on to a different one.
+    fn fetch_block(&mut self, ctx: &egui::Context, block_id: BlockId) -> JobId {
+        if let Some((fetching_id, job_id)) = self.block_job {
+            if fetching_id == block_id {
+                return job_id;
+            }
+            self.jobs.cancel(job_id);
+            self.jobs.forget(job_id);
+        }
+
+        let provider = self.provider.clone();
+        let job_id = self.jobs.spawn(ctx, Job::FetchBlock, |job_ctx| async move {
+            if job_ctx.is_cancelled() {
+                return Ok(None);
+            }
+            job_ctx.set_status("waiting for node");
+            provider.get_block(block_id).await.context("get_block")
+        });
+        self.block_job = Some((block_id, job_id));
+        job_id
+    }
+
+    /// Keeps a "latest block" fetch running on a timer while "Follow head"
+    /// is enabled, replacing it once `poll_interval_secs` has elapsed. If the
+    /// previous poll is still running when the timer fires, it's left alone
+    /// rather than cancelled, so a slow node doesn't get a cancel-and-restart
+    /// storm of overlapping requests.
+    fn poll_latest(&mut self, ctx: &egui::Context) -> JobId {
+        let now = ctx.input(|i| i.time);
+        let due = self.next_poll_at.map_or(true, |at| now >= at);
+
+        if due {
+            let still_running = self
+                .latest_job
+                .is_some_and(|job_id| self.jobs.is_running(job_id));
+
+            if !still_running {
+                if let Some(job_id) = self.latest_job {
+                    self.jobs.forget(job_id);
+                }
+
+                let provider = self.provider.clone();
+                let job_id = self.jobs.spawn(ctx, Job::FetchLatest, |job_ctx| async move {
+                    if job_ctx.is_cancelled() {
+                        return Ok(None);
+                    }
+                    job_ctx.set_status("waiting for node");
+                    provider
+                        .get_block(BlockId::Number(BlockNumber::Latest))
+                        .await
+                        .context("get_block")
+                });
+                self.latest_job = Some(job_id);
+            }
+            self.next_poll_at = Some(now + self.poll_interval_secs.max(1.0) as f64);
+        }
+
+        ctx.request_repaint_after(Duration::from_secs_f32(self.poll_interval_secs.max(1.0)));
+        self.latest_job
+            .expect("spawned on the first call since `due` starts true")
+    }
+}
+
+/// Renders a fetched block (or its error/loading state) the same way
+/// regardless of whether it came from the selector lookup or the "Follow
+/// head" poll.
+fn show_block_result(
+    ctx: &egui::Context,
+    ui: &mut egui::Ui,
+    result: Option<&BlockResult>,
+    kind: Option<Job>,
+    status: Option<&str>,
+) {
+    match result {
+        Some(Ok(Some(block))) => {
+            if let Some(block_number) = block.number {
+                ui.heading(format!("Block (number: {})", block_number));
+            } else {
+                ui.heading(format!("Block (pending)"));
+            }
+            ui.collapsing(
+                format!("Transactions ({})", block.transactions.len()),
+                |ui| {
+                    for trans in block.transactions.iter() {
+                        ui.label(format!("{}", trans));
+                    }
+                },
+            );
+        }
+        Some(Ok(None)) => {
+            ui.label("No block found with this ID");
+        }
+        Some(Err(err)) => {
+            ui.label(err.to_string());
+        }
+        None => {
+            // No result yet: keep the spinner animating until the
+            // background task wakes us up via `request_repaint`.
+            ctx.request_repaint_after(Duration::from_millis(100));
+            ui.horizontal(|ui| {
+                ui.spinner();
+                let label = status.or_else(|| kind.map(Job::label));
+                if let Some(label) = label {
+                    ui.label(label);
+                }
+            });
+        }
+    }
 }
 
 impl eframe::App for TemplateApp {
@@ -130,6 +262,9 @@ impl eframe::App for TemplateApp {
 
     /// Called each time the UI needs repainting, which may be many times per second.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.ensure_provider();
+        self.jobs.update(ctx);
+
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 ui.menu_button("File", |ui| {
@@ -141,6 +276,38 @@ impl eframe::App for TemplateApp {
 
                 egui::widgets::global_dark_light_mode_buttons(ui);
             });
+
+            ui.horizontal(|ui| {
+                ui.label("Network:");
+                egui::ComboBox::from_id_source("network_selector")
+                    .selected_text(
+                        NAMED_NETWORKS
+                            .iter()
+                            .find(|(_, url)| *url == self.rpc_url)
+                            .map_or("Custom", |(name, _)| name),
+                    )
+                    .show_ui(ui, |ui| {
+                        for (name, url) in NAMED_NETWORKS {
+                            if ui.selectable_label(self.rpc_url == *url, *name).clicked() {
+                                self.rpc_url = url.to_string();
+                                self.rpc_url_input = Some(url.to_string());
+                            }
+                        }
+                    });
+
+                if self.rpc_url_input.is_none() {
+                    self.rpc_url_input = Some(self.rpc_url.clone());
+                }
+                let input = self.rpc_url_input.as_mut().unwrap();
+                let response = ui.text_edit_singleline(input);
+                if response.lost_focus() {
+                    self.rpc_url = input.clone();
+                }
+            });
+
+            if let Some(err) = &self.provider_error {
+                ui.label(err.as_str());
+            }
         });
 
         egui::CentralPanel::default().show(ctx, |ui| {
@@ -148,47 +315,48 @@ impl eframe::App for TemplateApp {
 
             ui.separator();
 
-            ui.text_edit_singleline(&mut self.block_id_selector);
-
-            let block_id = BlockId::from_str(&self.block_id_selector);
+            ui.checkbox(&mut self.follow_head, "Follow head");
+            if self.follow_head {
+                ui.add(
+                    egui::Slider::new(&mut self.poll_interval_secs, 2.0..=60.0)
+                        .text("Poll interval (s)"),
+                );
+            } else {
+                ui.text_edit_singleline(&mut self.block_id_selector);
+            }
 
-            match block_id {
-                Ok(block_id) => {
-                    let provider = self.provider.clone();
-                    let cell_state = self.block.get_or_update(block_id, || async move {
-                        provider.get_block(block_id).await.context("get_block")
-                    });
+            ui.separator();
 
-                    match cell_state {
-                        CellState::Value(Ok(Some(block))) => {
-                            if let Some(block_number) = block.number {
-                                ui.heading(format!("Block (number: {})", block_number));
-                            } else {
-                                ui.heading(format!("Block (pending)"));
-                            }
-                            ui.collapsing(
-                                format!("Transactions ({})", block.transactions.len()),
-                                |ui| {
-                                    for trans in block.transactions.iter() {
-                                        ui.label(format!("{}", trans));
-                                    }
-                                },
+            // Don't issue fetches against a stale provider while the one
+            // built from the current `rpc_url` failed: the error is shown
+            // in the top panel above instead.
+            if self.provider_error.is_none() {
+                if self.follow_head {
+                    let job_id = self.poll_latest(ctx);
+                    show_block_result(
+                        ctx,
+                        ui,
+                        self.jobs.result(job_id),
+                        self.jobs.kind(job_id),
+                        self.jobs.status(job_id),
+                    );
+                } else {
+                    match BlockId::from_str(&self.block_id_selector) {
+                        Ok(block_id) => {
+                            let job_id = self.fetch_block(ctx, block_id);
+                            show_block_result(
+                                ctx,
+                                ui,
+                                self.jobs.result(job_id),
+                                self.jobs.kind(job_id),
+                                self.jobs.status(job_id),
                             );
                         }
-                        CellState::Value(Ok(None)) => {
-                            ui.label("No block found with this ID");
-                        }
-                        CellState::Value(Err(err)) => {
+                        Err(err) => {
                             ui.label(err.to_string());
                         }
-                        CellState::Running => {
-                            ui.spinner();
-                        }
                     }
                 }
-                Err(err) => {
-                    ui.label(err.to_string());
-                }
             }
         });
     }
@@ -0,0 +1,277 @@
+//! A small background-job manager, modelled on objdiff's `jobs` module:
+//! several [`Job`]s can run concurrently, each reporting a status string
+//! while it runs, and each cancellable without racing a stale result against
+//! a fresh one.
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::OnceLock;
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::runtime::Runtime;
+
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::sync::{mpsc, oneshot};
+#[cfg(target_arch = "wasm32")]
+use futures::channel::{mpsc, oneshot};
+
+#[cfg(not(target_arch = "wasm32"))]
+fn get_runtime() -> Arc<Runtime> {
+    static RUNTIME: OnceLock<Arc<Runtime>> = OnceLock::new();
+    RUNTIME
+        .get_or_init(|| Arc::new(Runtime::new().unwrap()))
+        .clone()
+}
+
+pub type JobId = u64;
+
+/// What a job is doing, used to label it in the UI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Job {
+    FetchBlock,
+    FetchLatest,
+}
+
+impl Job {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Job::FetchBlock => "Fetching block",
+            Job::FetchLatest => "Fetching latest block",
+        }
+    }
+}
+
+/// Handed to a job's future so it can report progress and notice it has
+/// been cancelled (e.g. because the user edited the block selector).
+#[derive(Clone)]
+pub struct JobContext {
+    cancel: Arc<AtomicBool>,
+    status: mpsc::UnboundedSender<String>,
+}
+
+impl JobContext {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    pub fn set_status(&self, status: impl Into<String>) {
+        let _ = self.status.send(status.into());
+    }
+}
+
+struct JobState<T> {
+    id: JobId,
+    kind: Job,
+    cancel: Arc<AtomicBool>,
+    status: Option<String>,
+    status_rx: mpsc::UnboundedReceiver<String>,
+    result_rx: oneshot::Receiver<T>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn try_recv_status(rx: &mut mpsc::UnboundedReceiver<String>) -> Option<String> {
+    rx.try_recv().ok()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn try_recv_status(rx: &mut mpsc::UnboundedReceiver<String>) -> Option<String> {
+    rx.try_next().ok().flatten()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn try_recv_result<T>(rx: &mut oneshot::Receiver<T>) -> Option<T> {
+    rx.try_recv().ok()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn try_recv_result<T>(rx: &mut oneshot::Receiver<T>) -> Option<T> {
+    rx.try_recv().ok().flatten()
+}
+
+/// Tracks every in-flight [`Job`] plus the results of finished ones, keyed
+/// by the [`JobId`] handed back from [`JobQueue::spawn`].
+pub struct JobQueue<T> {
+    jobs: Vec<JobState<T>>,
+    results: HashMap<JobId, T>,
+    next_id: JobId,
+}
+
+impl<T> Default for JobQueue<T> {
+    fn default() -> Self {
+        Self {
+            jobs: Default::default(),
+            results: Default::default(),
+            next_id: 0,
+        }
+    }
+}
+
+impl<T> JobQueue<T>
+where
+    T: 'static,
+{
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn spawn<FB, F>(&mut self, ctx: &egui::Context, kind: Job, future_builder: FB) -> JobId
+    where
+        T: Send,
+        FB: FnOnce(JobContext) -> F,
+        F: Future<Output = T> + Send + 'static,
+    {
+        let (cancel, status_tx, status_rx, result_tx, result_rx) = Self::new_channels();
+        let job_ctx = JobContext {
+            cancel: cancel.clone(),
+            status: status_tx,
+        };
+        let fut = future_builder(job_ctx);
+        let runtime = get_runtime();
+        let ctx = ctx.clone();
+        runtime.spawn(async move {
+            let value = fut.await;
+            let _ = result_tx.send(value);
+            ctx.request_repaint();
+        });
+
+        self.push_job(kind, cancel, status_rx, result_rx)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn spawn<FB, F>(&mut self, ctx: &egui::Context, kind: Job, future_builder: FB) -> JobId
+    where
+        FB: FnOnce(JobContext) -> F,
+        F: Future<Output = T> + 'static,
+    {
+        let (cancel, status_tx, status_rx, result_tx, result_rx) = Self::new_channels();
+        let job_ctx = JobContext {
+            cancel: cancel.clone(),
+            status: status_tx,
+        };
+        let fut = future_builder(job_ctx);
+        let ctx = ctx.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let value = fut.await;
+            let _ = result_tx.send(value);
+            ctx.request_repaint();
+        });
+
+        self.push_job(kind, cancel, status_rx, result_rx)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[allow(clippy::type_complexity)]
+    fn new_channels() -> (
+        Arc<AtomicBool>,
+        mpsc::UnboundedSender<String>,
+        mpsc::UnboundedReceiver<String>,
+        oneshot::Sender<T>,
+        oneshot::Receiver<T>,
+    ) {
+        let (status_tx, status_rx) = mpsc::unbounded_channel();
+        let (result_tx, result_rx) = oneshot::channel();
+        (Arc::new(AtomicBool::new(false)), status_tx, status_rx, result_tx, result_rx)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    #[allow(clippy::type_complexity)]
+    fn new_channels() -> (
+        Arc<AtomicBool>,
+        mpsc::UnboundedSender<String>,
+        mpsc::UnboundedReceiver<String>,
+        oneshot::Sender<T>,
+        oneshot::Receiver<T>,
+    ) {
+        let (status_tx, status_rx) = mpsc::unbounded();
+        let (result_tx, result_rx) = oneshot::channel();
+        (Arc::new(AtomicBool::new(false)), status_tx, status_rx, result_tx, result_rx)
+    }
+
+    fn push_job(
+        &mut self,
+        kind: Job,
+        cancel: Arc<AtomicBool>,
+        status_rx: mpsc::UnboundedReceiver<String>,
+        result_rx: oneshot::Receiver<T>,
+    ) -> JobId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.jobs.push(JobState {
+            id,
+            kind,
+            cancel,
+            status: None,
+            status_rx,
+            result_rx,
+        });
+        id
+    }
+
+    /// Marks a job as cancelled. Its future should observe
+    /// [`JobContext::is_cancelled`] and stop early; either way, its result
+    /// is discarded once it finishes rather than being kept around.
+    pub fn cancel(&mut self, id: JobId) {
+        if let Some(job) = self.jobs.iter().find(|j| j.id == id) {
+            job.cancel.store(true, Ordering::Relaxed);
+        }
+    }
+
+    pub fn kind(&self, id: JobId) -> Option<Job> {
+        self.jobs.iter().find(|j| j.id == id).map(|j| j.kind)
+    }
+
+    pub fn status(&self, id: JobId) -> Option<&str> {
+        self.jobs
+            .iter()
+            .find(|j| j.id == id)
+            .and_then(|j| j.status.as_deref())
+    }
+
+    pub fn is_running(&self, id: JobId) -> bool {
+        self.jobs.iter().any(|j| j.id == id)
+    }
+
+    /// Polls every running job: drains status updates, moves finished jobs'
+    /// results into the results map (unless they were cancelled), and drops
+    /// cancelled jobs once they complete. While any job is still running,
+    /// schedules a repaint so the spinner keeps animating and the result
+    /// appears as soon as it arrives, even if the user doesn't touch the UI.
+    pub fn update(&mut self, ctx: &egui::Context) {
+        let mut finished = Vec::new();
+
+        for job in &mut self.jobs {
+            while let Some(status) = try_recv_status(&mut job.status_rx) {
+                job.status = Some(status);
+            }
+
+            if let Some(value) = try_recv_result(&mut job.result_rx) {
+                if !job.cancel.load(Ordering::Relaxed) {
+                    self.results.insert(job.id, value);
+                }
+                finished.push(job.id);
+            }
+        }
+
+        self.jobs.retain(|job| !finished.contains(&job.id));
+
+        if !self.jobs.is_empty() {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+    }
+
+    pub fn result(&self, id: JobId) -> Option<&T> {
+        self.results.get(&id)
+    }
+
+    /// Drops a stored result, e.g. once a superseding job for the same
+    /// logical slot has been spawned and the old one is no longer needed.
+    /// Without this, `results` grows by one entry per finished job forever.
+    pub fn forget(&mut self, id: JobId) {
+        self.results.remove(&id);
+    }
+}